@@ -0,0 +1,349 @@
+use actix::prelude::*;
+use chrono::Utc;
+use diesel::prelude::*;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::{Signer, Verifier};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::{DbExecutor, PooledConn};
+use crate::models::{Article, NewArticle, User};
+use crate::prelude::*;
+
+// Article federation, modeled on Plume's `posts.rs`: local articles are wrapped
+// as ActivityStreams 2.0 `Article` objects and pushed to follower inboxes as
+// `Create`/`Update`/`Delete` activities, while the inbox ingests the same
+// activities from remote instances into the `articles` table.
+
+/// The lifecycle event being federated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verb {
+    Create,
+    Update,
+    Delete,
+}
+
+impl Verb {
+    fn as_str(self) -> &'static str {
+        match self {
+            Verb::Create => "Create",
+            Verb::Update => "Update",
+            Verb::Delete => "Delete",
+        }
+    }
+}
+
+/// The canonical `ap_url` for a locally authored article, derived from the
+/// instance base URL and the article slug.
+pub fn article_ap_url(slug: &str) -> String {
+    format!("{}/articles/{}", crate::config::base_url(), slug)
+}
+
+/// Build the AS2 `Article` object for `article`, including the original
+/// markdown `source`, hashtag `tag`s from its tag list and author attribution.
+fn article_object(article: &Article, author: &User, tags: &[String]) -> Value {
+    let hashtags = tags
+        .iter()
+        .map(|tag| {
+            json!({
+                "type": "Hashtag",
+                "name": format!("#{}", tag),
+                "href": format!("{}/tags/{}", crate::config::base_url(), tag),
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    json!({
+        "id": article.ap_url,
+        "type": "Article",
+        "name": article.title,
+        "summary": article.description,
+        "content": article.body,
+        "source": {
+            "content": article.source,
+            "mediaType": "text/markdown",
+        },
+        "attributedTo": author.ap_url,
+        "tag": hashtags,
+        "published": article.created_at.and_utc().to_rfc3339(),
+        "to": [PUBLIC_STREAM, format!("{}/followers", author.ap_url)],
+    })
+}
+
+fn wrap_activity(verb: Verb, author: &User, object: Value, object_id: &str) -> Value {
+    // Delete activities reference the object by id via a Tombstone; the others
+    // embed the full object.
+    let body = match verb {
+        Verb::Delete => json!({
+            "type": "Tombstone",
+            "id": object_id,
+        }),
+        _ => object,
+    };
+
+    json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": format!("{}/activity/{}", object_id, verb.as_str().to_lowercase()),
+        "type": verb.as_str(),
+        "actor": author.ap_url,
+        "object": body,
+        "to": [PUBLIC_STREAM, format!("{}/followers", author.ap_url)],
+    })
+}
+
+const PUBLIC_STREAM: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+/// Build and deliver the activity for `verb` on `article` to every follower
+/// inbox. Remote articles are never re-federated.
+pub fn federate_article(
+    verb: Verb,
+    article: &Article,
+    author: &User,
+    tags: &[String],
+    conn: &PooledConn,
+) -> Result<()> {
+    if article.is_remote {
+        return Ok(());
+    }
+
+    let object = article_object(article, author, tags);
+    let activity = wrap_activity(verb, author, object, &article.ap_url);
+    let body = serde_json::to_string(&activity).map_err(|_| Error::InternalServerError)?;
+
+    for inbox in follower_inboxes(author.id, conn)? {
+        // A single unreachable follower must not abort delivery to the rest.
+        if let Err(e) = sign_and_post(author, &inbox, &body) {
+            log::warn!("failed to deliver {} to {}: {}", verb.as_str(), inbox, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// The inbox URLs of every user following `author`.
+fn follower_inboxes(author_id: Uuid, conn: &PooledConn) -> Result<Vec<String>> {
+    use crate::schema::{followers, users};
+
+    followers::table
+        .inner_join(users::table.on(users::id.eq(followers::follower_id)))
+        .filter(followers::user_id.eq(author_id))
+        .select(users::inbox_url)
+        .load::<String>(conn)
+        .map_err(std::convert::Into::into)
+}
+
+/// POST `body` to `inbox`, signed with `author`'s private key using the
+/// draft-cavage HTTP signatures scheme that the fediverse expects.
+fn sign_and_post(author: &User, inbox: &str, body: &str) -> Result<()> {
+    let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+    let digest = digest_header(body);
+    let host = host_of(inbox)?;
+    let path = path_of(inbox)?;
+
+    let signing_string = format!(
+        "(request-target): post {}\nhost: {}\ndate: {}\ndigest: {}",
+        path, host, date, digest
+    );
+
+    let signature = {
+        let key = PKey::private_key_from_pem(author.private_key.as_bytes())
+            .map_err(|_| Error::InternalServerError)?;
+        let mut signer =
+            Signer::new(MessageDigest::sha256(), &key).map_err(|_| Error::InternalServerError)?;
+        signer
+            .update(signing_string.as_bytes())
+            .map_err(|_| Error::InternalServerError)?;
+        base64::encode(signer.sign_to_vec().map_err(|_| Error::InternalServerError)?)
+    };
+
+    let signature_header = format!(
+        r#"keyId="{}#main-key",algorithm="rsa-sha256",headers="(request-target) host date digest",signature="{}""#,
+        author.ap_url, signature
+    );
+
+    let client = reqwest::blocking::Client::new();
+    client
+        .post(inbox)
+        .header("Host", host)
+        .header("Date", date)
+        .header("Digest", digest)
+        .header("Signature", signature_header)
+        .header("Content-Type", "application/activity+json")
+        .body(body.to_owned())
+        .send()?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+fn digest_header(body: &str) -> String {
+    let hash = openssl::sha::sha256(body.as_bytes());
+    format!("SHA-256={}", base64::encode(hash))
+}
+
+fn host_of(url: &str) -> Result<String> {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_owned))
+        .ok_or_else(|| Error::InternalServerError)
+}
+
+fn path_of(url: &str) -> Result<String> {
+    reqwest::Url::parse(url)
+        .ok()
+        .map(|u| u.path().to_owned())
+        .ok_or_else(|| Error::InternalServerError)
+}
+
+// --- inbox -----------------------------------------------------------------
+
+/// A remote activity that arrived at this instance's inbox, already verified by
+/// the HTTP handler via [`verify_signature`].
+pub struct InboxIncoming {
+    pub activity: Value,
+}
+
+impl Message for InboxIncoming {
+    type Result = Result<()>;
+}
+
+impl Handler<InboxIncoming> for DbExecutor {
+    type Result = Result<()>;
+
+    fn handle(&mut self, msg: InboxIncoming, _: &mut Self::Context) -> Self::Result {
+        let conn = &self.0.get()?;
+
+        let verb = msg.activity["type"].as_str().unwrap_or_default();
+        match verb {
+            "Create" | "Update" => ingest_remote_article(&msg.activity["object"], conn),
+            "Delete" => delete_remote_article(&msg.activity["object"], conn),
+            other => Err(Error::UnprocessableEntity(json!({
+                "activity": [format!("unsupported activity type `{}`", other)],
+            }))),
+        }
+    }
+}
+
+/// Insert or update a remote `Article` object into the local `articles` table,
+/// flagged `is_remote` so it is distinguishable from locally authored posts.
+fn ingest_remote_article(object: &Value, conn: &PooledConn) -> Result<()> {
+    use crate::schema::articles;
+
+    let ap_url = object["id"]
+        .as_str()
+        .ok_or(Error::UnprocessableEntity(json!({ "object": ["missing id"] })))?;
+
+    // We don't fetch remote actors yet, so an article from an author we've
+    // never seen locally is skipped rather than failing the whole delivery.
+    let author = match remote_author(object, conn)? {
+        Some(author) => author,
+        None => {
+            log::info!("skipping remote article from unknown actor: {}", ap_url);
+            return Ok(());
+        }
+    };
+
+    let title = object["name"].as_str().unwrap_or_default().to_owned();
+    let body = object["content"].as_str().unwrap_or_default().to_owned();
+    let source = object["source"]["content"]
+        .as_str()
+        .unwrap_or(&body)
+        .to_owned();
+
+    let new_article = NewArticle {
+        id: Uuid::new_v4(),
+        author_id: author.id,
+        ap_url: ap_url.to_owned(),
+        slug: remote_slug(ap_url),
+        title,
+        description: object["summary"].as_str().unwrap_or_default().to_owned(),
+        source,
+        body,
+        lang: super::articles::normalize_lang(
+            object["contentMap"]
+                .as_object()
+                .and_then(|m| m.keys().next().map(String::as_str))
+                .unwrap_or("eng"),
+        ),
+        is_remote: true,
+    };
+
+    diesel::insert_into(articles::table)
+        .values(&new_article)
+        .on_conflict(articles::ap_url)
+        .do_update()
+        .set((
+            articles::title.eq(&new_article.title),
+            articles::description.eq(&new_article.description),
+            articles::body.eq(&new_article.body),
+            articles::source.eq(&new_article.source),
+            articles::lang.eq(&new_article.lang),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+fn delete_remote_article(object: &Value, conn: &PooledConn) -> Result<()> {
+    use crate::schema::articles;
+
+    let ap_url = object["id"]
+        .as_str()
+        .ok_or(Error::UnprocessableEntity(json!({ "object": ["missing id"] })))?;
+
+    diesel::delete(
+        articles::table
+            .filter(articles::ap_url.eq(ap_url))
+            .filter(articles::is_remote.eq(true)),
+    )
+    .execute(conn)?;
+
+    Ok(())
+}
+
+/// The local `users` row for the article object's `attributedTo` actor, or `None`
+/// when we've never seen that actor (remote actors aren't fetched yet).
+fn remote_author(object: &Value, conn: &PooledConn) -> Result<Option<User>> {
+    use crate::schema::users;
+
+    let actor = object["attributedTo"]
+        .as_str()
+        .ok_or(Error::UnprocessableEntity(json!({ "object": ["missing attributedTo"] })))?;
+
+    users::table
+        .filter(users::ap_url.eq(actor))
+        .get_result::<User>(conn)
+        .optional()
+        .map_err(std::convert::Into::into)
+}
+
+/// Derive a local slug for a remote article. The slug is qualified with the
+/// origin host so two instances publishing the same title can't collide on the
+/// `articles.slug` unique index and silently drop one another's posts.
+fn remote_slug(ap_url: &str) -> String {
+    let tail = ap_url
+        .rsplit('/')
+        .next()
+        .map(str::to_owned)
+        .unwrap_or_else(|| ap_url.to_owned());
+
+    match host_of(ap_url) {
+        Ok(host) => format!("{}@{}", tail, host),
+        Err(_) => tail,
+    }
+}
+
+/// Verify the HTTP signature on an incoming request against `public_key_pem`.
+/// Returns `true` when the signature is valid for `signing_string`.
+pub fn verify_signature(
+    public_key_pem: &str,
+    signing_string: &str,
+    signature_b64: &str,
+) -> Result<bool> {
+    let key = PKey::public_key_from_pem(public_key_pem.as_bytes())?;
+    let signature = base64::decode(signature_b64).map_err(|_| Error::Unauthorized)?;
+    let mut verifier = Verifier::new(MessageDigest::sha256(), &key)?;
+    verifier.update(signing_string.as_bytes())?;
+    Ok(verifier.verify(&signature)?)
+}
@@ -1,6 +1,8 @@
 use actix::prelude::*;
 use blob_uuid::{to_blob, to_uuid};
 use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel::BoxableExpression;
 use slug::slugify;
 use uuid::Uuid;
 
@@ -37,13 +39,26 @@ impl Handler<CreateArticleOuter> for DbExecutor {
         let new_article_id = Uuid::new_v4();
         let slug = generate_slug(&new_article_id, &msg.article.title);
 
+        // Take the language from the request if the author set one, otherwise
+        // guess it from the body so multilingual instances can still filter.
+        let lang = msg
+            .article
+            .lang
+            .clone()
+            .map(|lang| normalize_lang(&lang))
+            .unwrap_or_else(|| detect_lang(&msg.article.body));
+
         let new_article = NewArticle {
             id: new_article_id,
             author_id: author.id,
+            ap_url: super::activitypub::article_ap_url(&slug),
             slug,
             title: msg.article.title,
             description: msg.article.description,
+            source: msg.article.body.clone(),
             body: msg.article.body,
+            lang,
+            is_remote: false,
         };
         let article = diesel::insert_into(articles::table)
             .values(&new_article)
@@ -55,12 +70,22 @@ impl Handler<CreateArticleOuter> for DbExecutor {
             .map(|article_tag| article_tag.tag_name.to_owned())
             .collect::<Vec<String>>();
 
+        // Announce the new article to the author's followers on the fediverse.
+        super::activitypub::federate_article(
+            super::activitypub::Verb::Create,
+            &article,
+            &author,
+            &tags,
+            conn,
+        )?;
+
         Ok(ArticleResponse {
             article: ArticleResponseInner {
                 slug: article.slug,
                 title: article.title,
                 description: article.description,
                 body: article.body,
+                lang: article.lang.clone(),
                 tag_list: tags,
                 created_at: CustomDateTime(article.created_at),
                 updated_at: CustomDateTime(article.updated_at),
@@ -109,6 +134,7 @@ impl Handler<GetArticle> for DbExecutor {
                 title: article.title,
                 description: article.description,
                 body: article.body,
+                lang: article.lang.clone(),
                 tag_list: tags,
                 created_at: CustomDateTime(article.created_at),
                 updated_at: CustomDateTime(article.updated_at),
@@ -158,7 +184,11 @@ impl Handler<UpdateArticleOuter> for DbExecutor {
             slug,
             title: msg.article.title,
             description: msg.article.description,
+            // Keep `source` (the original markdown federated as AS2
+            // `source.content`) in step with the edited body.
+            source: msg.article.body.clone(),
             body: msg.article.body,
+            lang: msg.article.lang.map(|lang| normalize_lang(&lang)),
         };
 
         let article = diesel::update(articles::table.find(article.id))
@@ -180,12 +210,22 @@ impl Handler<UpdateArticleOuter> for DbExecutor {
 
         let favorited = get_favorited(article.id, author.id, conn)?;
 
+        // Push the edit out to the author's followers.
+        super::activitypub::federate_article(
+            super::activitypub::Verb::Update,
+            &article,
+            &author,
+            &tags,
+            conn,
+        )?;
+
         Ok(ArticleResponse {
             article: ArticleResponseInner {
                 slug: article.slug,
                 title: article.title,
                 description: article.description,
                 body: article.body,
+                lang: article.lang.clone(),
                 tag_list: tags,
                 created_at: CustomDateTime(article.created_at),
                 updated_at: CustomDateTime(article.updated_at),
@@ -226,6 +266,17 @@ impl Handler<DeleteArticle> for DbExecutor {
             })));
         }
 
+        // Tell the fediverse before the row (and its tags) are gone, since the
+        // Delete activity is built from them.
+        let tags = select_tags_on_article(article.id, conn)?;
+        super::activitypub::federate_article(
+            super::activitypub::Verb::Delete,
+            &article,
+            &author,
+            &tags,
+            conn,
+        )?;
+
         delete_tags(article.id, conn)?;
 
         delete_favorites(article.id, conn)?;
@@ -274,6 +325,7 @@ impl Handler<FavoriteArticle> for DbExecutor {
                 title: article.title,
                 description: article.description,
                 body: article.body,
+                lang: article.lang.clone(),
                 tag_list: tags,
                 created_at: CustomDateTime(article.created_at),
                 updated_at: CustomDateTime(article.updated_at),
@@ -325,6 +377,7 @@ impl Handler<UnfavoriteArticle> for DbExecutor {
                 title: article.title,
                 description: article.description,
                 body: article.body,
+                lang: article.lang.clone(),
                 tag_list: tags,
                 created_at: CustomDateTime(article.created_at),
                 updated_at: CustomDateTime(article.updated_at),
@@ -349,11 +402,97 @@ impl Handler<GetArticles> for DbExecutor {
     type Result = Result<ArticleListResponse>;
 
     fn handle(&mut self, msg: GetArticles, _: &mut Self::Context) -> Self::Result {
-        use crate::schema::articles;
+        use crate::schema::{article_tags, articles, favorite_articles, users};
 
         let conn = &self.0.get()?;
 
-        unimplemented!()
+        // Start from a boxed query and chain a predicate for each present
+        // filter, rather than branching into a handful of hand-written queries.
+        let mut query = articles::table.into_boxed();
+
+        if let Some(ref author_name) = msg.author {
+            // An unknown author isn't an error: per the RealWorld contract a
+            // filter that matches no user yields an empty list, not a 404.
+            match users::table
+                .filter(users::username.eq(author_name))
+                .first::<User>(conn)
+                .optional()?
+            {
+                Some(author) => query = query.filter(Article::with_author_id(author.id)),
+                None => return Ok(empty_article_list()),
+            }
+        }
+
+        if let Some(ref tag_name) = msg.tag {
+            let tagged = article_tags::table
+                .filter(article_tags::tag_name.eq(tag_name))
+                .select(article_tags::article_id);
+            query = query.filter(articles::id.eq_any(tagged));
+        }
+
+        if let Some(ref favorited_name) = msg.favorited {
+            let favorited_by = match users::table
+                .filter(users::username.eq(favorited_name))
+                .first::<User>(conn)
+                .optional()?
+            {
+                Some(user) => user,
+                None => return Ok(empty_article_list()),
+            };
+            let favorited = favorite_articles::table
+                .filter(favorite_articles::user_id.eq(favorited_by.id))
+                .select(favorite_articles::article_id);
+            query = query.filter(articles::id.eq_any(favorited));
+        }
+
+        if let Some(ref lang) = msg.lang {
+            query = query.filter(articles::lang.eq(normalize_lang(lang)));
+        }
+
+        let articles_list = query
+            .order(articles::created_at.desc())
+            .limit(msg.limit as i64)
+            .offset(msg.offset as i64)
+            .load::<Article>(conn)?;
+
+        let mut articles = Vec::with_capacity(articles_list.len());
+
+        for article in articles_list {
+            let author = users_table_find(article.author_id, conn)?;
+
+            let (favorited, following) = match &msg.auth {
+                Some(auth) => {
+                    get_favorited_and_following(article.id, author.id, auth.user.id, conn)?
+                }
+                None => (false, false),
+            };
+            let favorites_count = get_favorites_count(article.id, conn)?;
+            let tags = select_tags_on_article(article.id, conn)?;
+
+            articles.push(ArticleResponseInner {
+                slug: article.slug,
+                title: article.title,
+                description: article.description,
+                body: article.body,
+                lang: article.lang.clone(),
+                tag_list: tags,
+                created_at: CustomDateTime(article.created_at),
+                updated_at: CustomDateTime(article.updated_at),
+                favorited,
+                favorites_count,
+                author: ProfileResponseInner {
+                    username: author.username,
+                    bio: author.bio,
+                    image: author.image,
+                    following,
+                },
+            });
+        }
+
+        Ok(ArticleListResponse {
+            articles_count: articles.len(),
+            articles,
+        })
     }
 }
 
@@ -365,11 +504,173 @@ impl Handler<GetFeed> for DbExecutor {
     type Result = Result<ArticleListResponse>;
 
     fn handle(&mut self, msg: GetFeed, _: &mut Self::Context) -> Self::Result {
+        use crate::schema::{articles, followers};
+
+        let conn = &self.0.get()?;
+
+        let user_id = msg.auth.user.id;
+
+        // The feed is every article whose author the requesting user follows,
+        // most recent first.
+        let mut query = articles::table
+            .inner_join(
+                followers::table.on(followers::user_id
+                    .eq(articles::author_id)
+                    .and(followers::follower_id.eq(user_id))),
+            )
+            .select(articles::all_columns)
+            .into_boxed();
+
+        if let Some(ref lang) = msg.lang {
+            query = query.filter(articles::lang.eq(normalize_lang(lang)));
+        }
+
+        let articles_list = query
+            .order(articles::created_at.desc())
+            .limit(msg.limit as i64)
+            .offset(msg.offset as i64)
+            .load::<Article>(conn)?;
+
+        let mut articles = Vec::with_capacity(articles_list.len());
+
+        for article in articles_list {
+            let author = users_table_find(article.author_id, conn)?;
+
+            let favorited = get_favorited(article.id, user_id, conn)?;
+            let favorites_count = get_favorites_count(article.id, conn)?;
+            let tags = select_tags_on_article(article.id, conn)?;
+
+            articles.push(ArticleResponseInner {
+                slug: article.slug,
+                title: article.title,
+                description: article.description,
+                body: article.body,
+                lang: article.lang.clone(),
+                tag_list: tags,
+                created_at: CustomDateTime(article.created_at),
+                updated_at: CustomDateTime(article.updated_at),
+                favorited,
+                favorites_count,
+                author: ProfileResponseInner {
+                    username: author.username,
+                    bio: author.bio,
+                    image: author.image,
+                    following: true, // <- authors in the feed are followed by definition
+                },
+            });
+        }
+
+        Ok(ArticleListResponse {
+            articles_count: articles.len(),
+            articles,
+        })
+    }
+}
+
+// Ranked full-text search across an article's title, description and body.
+// Backed by a trigger-maintained `search_vector` tsvector column with a GIN
+// index (see the accompanying migration); ranking is by `ts_rank` so multi-term
+// and partial-word queries beat a plain `LIKE`.
+pub struct SearchArticles {
+    pub auth: Option<Auth>,
+    pub query: String,
+    pub lang: Option<String>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl Message for SearchArticles {
+    type Result = Result<ArticleListResponse>;
+}
+
+impl Handler<SearchArticles> for DbExecutor {
+    type Result = Result<ArticleListResponse>;
+
+    fn handle(&mut self, msg: SearchArticles, _: &mut Self::Context) -> Self::Result {
         use crate::schema::articles;
+        use diesel_full_text_search::{plainto_tsquery_with_search_config, ts_rank, TsVectorExtensions};
 
         let conn = &self.0.get()?;
 
-        unimplemented!()
+        // The optional `lang` picks the text-search configuration, defaulting
+        // to English stemming when unset.
+        let config = msg.lang.unwrap_or_else(|| "english".to_owned());
+        let ts_query = plainto_tsquery_with_search_config(config, &msg.query);
+
+        let articles_list = articles::table
+            .filter(articles::search_vector.matches(ts_query.clone()))
+            .order(ts_rank(articles::search_vector, ts_query).desc())
+            .limit(msg.limit as i64)
+            .offset(msg.offset as i64)
+            .load::<Article>(conn)?;
+
+        let mut articles = Vec::with_capacity(articles_list.len());
+
+        for article in articles_list {
+            let author = users_table_find(article.author_id, conn)?;
+
+            let (favorited, following) = match &msg.auth {
+                Some(auth) => {
+                    get_favorited_and_following(article.id, author.id, auth.user.id, conn)?
+                }
+                None => (false, false),
+            };
+            let favorites_count = get_favorites_count(article.id, conn)?;
+            let tags = select_tags_on_article(article.id, conn)?;
+
+            articles.push(ArticleResponseInner {
+                slug: article.slug,
+                title: article.title,
+                description: article.description,
+                body: article.body,
+                lang: article.lang.clone(),
+                tag_list: tags,
+                created_at: CustomDateTime(article.created_at),
+                updated_at: CustomDateTime(article.updated_at),
+                favorited,
+                favorites_count,
+                author: ProfileResponseInner {
+                    username: author.username,
+                    bio: author.bio,
+                    image: author.image,
+                    following,
+                },
+            });
+        }
+
+        Ok(ArticleListResponse {
+            articles_count: articles.len(),
+            articles,
+        })
+    }
+}
+
+// A boxed boolean condition over `articles`, so the predicate helpers below
+// can be mixed and matched with `.filter(...)` on a boxed query.
+type BoxedArticleCondition =
+    Box<dyn BoxableExpression<crate::schema::articles::table, diesel::pg::Pg, SqlType = Bool>>;
+
+impl Article {
+    fn with_author_id(author_id: Uuid) -> BoxedArticleCondition {
+        use crate::schema::articles;
+        Box::new(articles::author_id.eq(author_id))
+    }
+}
+
+pub(crate) fn users_table_find(user_id: Uuid, conn: &PooledConn) -> Result<User> {
+    use crate::schema::users;
+
+    users::table
+        .find(user_id)
+        .get_result::<User>(conn)
+        .map_err(std::convert::Into::into)
+}
+
+/// An empty listing, returned when a filter names a user that doesn't exist.
+fn empty_article_list() -> ArticleListResponse {
+    ArticleListResponse {
+        articles_count: 0,
+        articles: Vec::new(),
     }
 }
 
@@ -377,6 +678,68 @@ fn generate_slug(uuid: &Uuid, title: &str) -> String {
     format!("{}-{}", to_blob(uuid), slugify(title))
 }
 
+/// Best-effort language detection from an article's body, falling back to
+/// English when the text is too short to classify. The result is normalized to
+/// the same code space as client-supplied and filter languages (see
+/// [`normalize_lang`]).
+fn detect_lang(body: &str) -> String {
+    let code = whatlang::detect_lang(body)
+        .map(|lang| lang.code().to_owned())
+        .unwrap_or_else(|| "eng".to_owned());
+    normalize_lang(&code)
+}
+
+/// Normalize a language code to ISO 639-1 (two-letter) form so that
+/// auto-detected languages — which `whatlang` reports as ISO 639-3 ("eng") —
+/// compare equal to the two-letter codes clients send and filter on ("en").
+/// Codes outside this common set are passed through unchanged; clients should
+/// then use the same code space for those languages.
+pub(crate) fn normalize_lang(code: &str) -> String {
+    // Reduce a BCP-47 tag to its lowercased primary subtag first, so
+    // `en-US`/`EN` collapse to `en` before the 639-3 → 639-1 mapping.
+    let primary = code
+        .split(|c| c == '-' || c == '_')
+        .next()
+        .unwrap_or(code)
+        .to_lowercase();
+
+    let normalized = match primary.as_str() {
+        "eng" => "en",
+        // The Norwegian macrolanguage code clients often send maps to the same
+        // two-letter code whatlang's `nob` normalizes to.
+        "no" | "nob" => "nb",
+        "spa" => "es",
+        "por" => "pt",
+        "fra" => "fr",
+        "deu" => "de",
+        "ita" => "it",
+        "nld" => "nl",
+        "rus" => "ru",
+        "ukr" => "uk",
+        "pol" => "pl",
+        "ces" => "cs",
+        "ron" => "ro",
+        "swe" => "sv",
+        "dan" => "da",
+        "fin" => "fi",
+        "hun" => "hu",
+        "ell" => "el",
+        "tur" => "tr",
+        "arb" => "ar",
+        "heb" => "he",
+        "hin" => "hi",
+        "ben" => "bn",
+        "jpn" => "ja",
+        "kor" => "ko",
+        "cmn" => "zh",
+        "vie" => "vi",
+        "ind" => "id",
+        "tha" => "th",
+        other => other,
+    };
+    normalized.to_owned()
+}
+
 fn add_tag<T>(article_id: Uuid, tag_name: T, conn: &PooledConn) -> Result<ArticleTag>
 where
     T: ToString,
@@ -423,7 +786,7 @@ where
         .collect::<Result<Vec<ArticleTag>>>()
 }
 
-fn get_favorites_count(article_id: Uuid, conn: &PooledConn) -> Result<usize> {
+pub(crate) fn get_favorites_count(article_id: Uuid, conn: &PooledConn) -> Result<usize> {
     use crate::schema::favorite_articles;
 
     let favorites_count = favorite_articles::table
@@ -433,7 +796,7 @@ fn get_favorites_count(article_id: Uuid, conn: &PooledConn) -> Result<usize> {
     Ok(favorites_count as usize)
 }
 
-fn get_favorited(article_id: Uuid, user_id: Uuid, conn: &PooledConn) -> Result<bool> {
+pub(crate) fn get_favorited(article_id: Uuid, user_id: Uuid, conn: &PooledConn) -> Result<bool> {
     use crate::schema::{favorite_articles, users};
 
     let (_, favorite_id) = users::table
@@ -449,7 +812,7 @@ fn get_favorited(article_id: Uuid, user_id: Uuid, conn: &PooledConn) -> Result<b
     Ok(favorite_id.is_some())
 }
 
-fn get_favorited_and_following(
+pub(crate) fn get_favorited_and_following(
     article_id: Uuid,
     author_id: Uuid,
     user_id: Uuid,
@@ -480,7 +843,7 @@ fn get_favorited_and_following(
     Ok((favorite_id.is_some(), follow_id.is_some()))
 }
 
-fn select_tags_on_article(article_id: Uuid, conn: &PooledConn) -> Result<Vec<String>> {
+pub(crate) fn select_tags_on_article(article_id: Uuid, conn: &PooledConn) -> Result<Vec<String>> {
     use crate::schema::article_tags;
 
     let tags = article_tags::table
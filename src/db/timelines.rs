@@ -0,0 +1,836 @@
+use actix::prelude::*;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel::BoxableExpression;
+use uuid::Uuid;
+
+use super::articles::{
+    get_favorited_and_following, get_favorites_count, select_tags_on_article, users_table_find,
+};
+use super::lists::{find_list, kind_of, list_elements, ListKind};
+use super::{DbExecutor, PooledConn};
+use crate::app::articles::{ArticleListResponse, ArticleResponseInner};
+use crate::app::profiles::ProfileResponseInner;
+use crate::models::{Article, NewTimeline, Timeline, User};
+use crate::prelude::*;
+use crate::utils::CustomDateTime;
+
+// A user-defined timeline is nothing more than a saved filter query. The query
+// language is lifted from Plume: a boolean combination of atomic predicates
+// over an article's tags, author, language and favorite status.
+
+/// A reference to a set of values, either written inline (`["rust", "actix"]`)
+/// or pointing at a named list (`@my-authors`) that will be resolved at compile
+/// time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListRef {
+    Inline(Vec<String>),
+    Named(String),
+}
+
+/// The parsed form of a timeline query string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineQuery {
+    And(Box<TimelineQuery>, Box<TimelineQuery>),
+    Or(Box<TimelineQuery>, Box<TimelineQuery>),
+    Not(Box<TimelineQuery>),
+    Tag(ListRef),
+    Author(ListRef),
+    Lang(ListRef),
+    /// Match articles whose title/body contains any whole word in the list.
+    Word(ListRef),
+    /// Match articles whose title/body contains a word starting with any
+    /// value in the list.
+    Prefix(ListRef),
+    Favorited,
+}
+
+/// A parse failure carrying the character offset at which it was detected, so the
+/// caller can point the user at the offending part of their query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for QueryParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "at position {}: {}", self.position, self.message)
+    }
+}
+
+impl From<QueryParseError> for Error {
+    fn from(e: QueryParseError) -> Self {
+        Error::UnprocessableEntity(json!({ "query": [e.to_string()] }))
+    }
+}
+
+impl TimelineQuery {
+    /// Parse a query string into an AST, reporting a position-aware error on
+    /// malformed input.
+    pub fn parse(input: &str) -> std::result::Result<TimelineQuery, QueryParseError> {
+        let tokens = lex(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.parse_or()?;
+        if let Some(tok) = parser.peek() {
+            return Err(QueryParseError {
+                position: tok.position,
+                message: format!("unexpected trailing input `{}`", tok.lexeme()),
+            });
+        }
+        Ok(query)
+    }
+
+    /// Replace every `@list` reference with the values the list currently holds,
+    /// so the compiled query is a plain value-set match. Errors if a referenced
+    /// list does not belong to `user_id` or is of a different kind than its
+    /// predicate expects (e.g. `author in @my-tags`).
+    fn resolve(self, user_id: Uuid, conn: &PooledConn) -> Result<TimelineQuery> {
+        Ok(match self {
+            TimelineQuery::And(l, r) => TimelineQuery::And(
+                Box::new(l.resolve(user_id, conn)?),
+                Box::new(r.resolve(user_id, conn)?),
+            ),
+            TimelineQuery::Or(l, r) => TimelineQuery::Or(
+                Box::new(l.resolve(user_id, conn)?),
+                Box::new(r.resolve(user_id, conn)?),
+            ),
+            TimelineQuery::Not(inner) => {
+                TimelineQuery::Not(Box::new(inner.resolve(user_id, conn)?))
+            }
+            TimelineQuery::Tag(list) => {
+                TimelineQuery::Tag(resolve_list(list, Some(ListKind::Tag), user_id, conn)?)
+            }
+            TimelineQuery::Author(list) => {
+                TimelineQuery::Author(resolve_list(list, Some(ListKind::Author), user_id, conn)?)
+            }
+            // `lang` has no corresponding list kind, so any list of codes is
+            // accepted.
+            TimelineQuery::Lang(list) => {
+                TimelineQuery::Lang(resolve_list(list, None, user_id, conn)?)
+            }
+            TimelineQuery::Word(list) => {
+                TimelineQuery::Word(resolve_list(list, Some(ListKind::Word), user_id, conn)?)
+            }
+            TimelineQuery::Prefix(list) => {
+                TimelineQuery::Prefix(resolve_list(list, Some(ListKind::Prefix), user_id, conn)?)
+            }
+            TimelineQuery::Favorited => TimelineQuery::Favorited,
+        })
+    }
+
+    /// Compile the AST into a boxed boolean condition over `articles`, joining
+    /// through `article_tags`/`favorite_articles`/`users` as each predicate
+    /// requires. `viewer_id` is the id of the user the timeline belongs to,
+    /// used to evaluate `favorited`.
+    fn compile(&self, viewer_id: Uuid) -> BoxedArticleCondition {
+        use crate::schema::{article_tags, articles, favorite_articles, users};
+
+        match self {
+            TimelineQuery::And(l, r) => {
+                Box::new(l.compile(viewer_id).and(r.compile(viewer_id)))
+            }
+            TimelineQuery::Or(l, r) => Box::new(l.compile(viewer_id).or(r.compile(viewer_id))),
+            TimelineQuery::Not(inner) => Box::new(diesel::dsl::not(inner.compile(viewer_id))),
+            TimelineQuery::Tag(list) => {
+                let tagged = article_tags::table
+                    .filter(article_tags::tag_name.eq_any(list.values()))
+                    .select(article_tags::article_id);
+                Box::new(articles::id.eq_any(tagged))
+            }
+            TimelineQuery::Author(list) => {
+                let authors = users::table
+                    .filter(users::username.eq_any(list.values()))
+                    .select(users::id);
+                Box::new(articles::author_id.eq_any(authors))
+            }
+            TimelineQuery::Lang(list) => Box::new(articles::lang.eq_any(list.values())),
+            // Word/prefix lists match against the title/description/body
+            // `search_vector` maintained for full-text search (see chunk0-5),
+            // OR-ing the values together. Prefix lists use tsquery's `:*`
+            // operator so `act` matches `actix`.
+            TimelineQuery::Word(list) => text_match(list, false),
+            TimelineQuery::Prefix(list) => text_match(list, true),
+            TimelineQuery::Favorited => {
+                let favorited = favorite_articles::table
+                    .filter(favorite_articles::user_id.eq(viewer_id))
+                    .select(favorite_articles::article_id);
+                Box::new(articles::id.eq_any(favorited))
+            }
+        }
+    }
+}
+
+impl ListRef {
+    /// The concrete set of values this reference resolves to. Named lists are
+    /// inlined by [`TimelineQuery::resolve`] each time a stored timeline is
+    /// read, before its query is compiled; an unresolved named list yields no
+    /// values.
+    fn values(&self) -> Vec<String> {
+        match self {
+            ListRef::Inline(values) => values.clone(),
+            ListRef::Named(_) => Vec::new(),
+        }
+    }
+}
+
+/// Resolve `list` to its concrete values. Named lists are looked up for
+/// `user_id` and, when `expected` is set, checked to be of that kind so a
+/// predicate can't match against values of the wrong sort.
+fn resolve_list(
+    list: ListRef,
+    expected: Option<ListKind>,
+    user_id: Uuid,
+    conn: &PooledConn,
+) -> Result<ListRef> {
+    match list {
+        ListRef::Inline(values) => Ok(ListRef::Inline(values)),
+        ListRef::Named(name) => {
+            let list = find_list(&name, user_id, conn)?;
+            if let Some(expected) = expected {
+                let kind = kind_of(&list)?;
+                if kind != expected {
+                    return Err(Error::UnprocessableEntity(json!({
+                        "query": [format!(
+                            "list `{}` is a {} list, but a {} list is required here",
+                            name,
+                            kind.as_str(),
+                            expected.as_str(),
+                        )],
+                    })));
+                }
+            }
+            Ok(ListRef::Inline(list_elements(list.id, conn)?))
+        }
+    }
+}
+
+/// The text-search configuration the `search_vector` column is built with (see
+/// chunk0-5); word/prefix matching must use the same one to line up stemming.
+const TS_CONFIG: &str = "english";
+
+/// Compile a word/prefix list into a full-text condition over `search_vector`,
+/// OR-ing one match per value. Word values go through `plainto_tsquery`, which
+/// safely tolerates arbitrary text; prefix values are reduced to a single
+/// lexeme and matched with tsquery's `:*` operator. An empty list matches
+/// nothing.
+fn text_match(list: &ListRef, prefix: bool) -> BoxedArticleCondition {
+    use crate::schema::articles;
+    use diesel_full_text_search::{
+        plainto_tsquery_with_search_config, to_tsquery_with_search_config, TsVectorExtensions,
+    };
+
+    let mut condition: Option<BoxedArticleCondition> = None;
+
+    for value in list.values() {
+        let term: BoxedArticleCondition = if prefix {
+            match prefix_lexeme(&value) {
+                Some(lexeme) => Box::new(articles::search_vector.matches(
+                    to_tsquery_with_search_config(TS_CONFIG.to_owned(), format!("{}:*", lexeme)),
+                )),
+                None => continue,
+            }
+        } else {
+            Box::new(
+                articles::search_vector
+                    .matches(plainto_tsquery_with_search_config(TS_CONFIG.to_owned(), &value)),
+            )
+        };
+
+        condition = Some(match condition {
+            Some(existing) => Box::new(existing.or(term)),
+            None => term,
+        });
+    }
+
+    // No (usable) values means the predicate selects nothing, mirroring an
+    // empty `eq_any` on the other predicates.
+    condition.unwrap_or_else(|| Box::new(articles::id.eq_any(Vec::<Uuid>::new())))
+}
+
+/// Reduce a prefix value to the first run of alphanumeric characters,
+/// lowercased, so it forms a single safe tsquery lexeme. Returns `None` when
+/// the value has no such run.
+fn prefix_lexeme(value: &str) -> Option<String> {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .find(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+type BoxedArticleCondition =
+    Box<dyn BoxableExpression<crate::schema::articles::table, diesel::pg::Pg, SqlType = Bool>>;
+
+// --- lexer -----------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    And,
+    Or,
+    Not,
+    In,
+    Favorited,
+    At,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl Token {
+    fn lexeme(&self) -> String {
+        match self {
+            Token::Ident(s) => s.to_owned(),
+            Token::Str(s) => format!("\"{}\"", s),
+            Token::And => "and".into(),
+            Token::Or => "or".into(),
+            Token::Not => "not".into(),
+            Token::In => "in".into(),
+            Token::Favorited => "favorited".into(),
+            Token::At => "@".into(),
+            Token::LBracket => "[".into(),
+            Token::RBracket => "]".into(),
+            Token::LParen => "(".into(),
+            Token::RParen => ")".into(),
+            Token::Comma => ",".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Spanned {
+    token: Token,
+    position: usize,
+}
+
+impl Spanned {
+    fn lexeme(&self) -> String {
+        self.token.lexeme()
+    }
+}
+
+fn lex(input: &str) -> std::result::Result<Vec<Spanned>, QueryParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        match c {
+            '[' => {
+                tokens.push(Spanned { token: Token::LBracket, position: start });
+                i += 1;
+            }
+            ']' => {
+                tokens.push(Spanned { token: Token::RBracket, position: start });
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Spanned { token: Token::LParen, position: start });
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Spanned { token: Token::RParen, position: start });
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Spanned { token: Token::Comma, position: start });
+                i += 1;
+            }
+            '@' => {
+                tokens.push(Spanned { token: Token::At, position: start });
+                i += 1;
+            }
+            '"' => {
+                i += 1;
+                let mut value = String::new();
+                loop {
+                    match chars.get(i) {
+                        Some('"') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(ch) => {
+                            value.push(*ch);
+                            i += 1;
+                        }
+                        None => {
+                            return Err(QueryParseError {
+                                position: start,
+                                message: "unterminated string literal".into(),
+                            });
+                        }
+                    }
+                }
+                tokens.push(Spanned { token: Token::Str(value), position: start });
+            }
+            c if is_ident_char(c) => {
+                let mut word = String::new();
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    word.push(chars[i]);
+                    i += 1;
+                }
+                let token = match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "in" => Token::In,
+                    "favorited" => Token::Favorited,
+                    _ => Token::Ident(word),
+                };
+                tokens.push(Spanned { token, position: start });
+            }
+            other => {
+                return Err(QueryParseError {
+                    position: start,
+                    message: format!("unexpected character `{}`", other),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+// --- parser ----------------------------------------------------------------
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Spanned> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Spanned> {
+        let tok = self.tokens.get(self.pos).cloned();
+        if tok.is_some() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn end_position(&self) -> usize {
+        self.tokens
+            .last()
+            .map(|t| t.position + t.lexeme().chars().count())
+            .unwrap_or(0)
+    }
+
+    fn parse_or(&mut self) -> std::result::Result<TimelineQuery, QueryParseError> {
+        let mut left = self.parse_and()?;
+        while matches!(self.peek().map(|s| &s.token), Some(Token::Or)) {
+            self.next();
+            let right = self.parse_and()?;
+            left = TimelineQuery::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> std::result::Result<TimelineQuery, QueryParseError> {
+        let mut left = self.parse_unary()?;
+        while matches!(self.peek().map(|s| &s.token), Some(Token::And)) {
+            self.next();
+            let right = self.parse_unary()?;
+            left = TimelineQuery::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> std::result::Result<TimelineQuery, QueryParseError> {
+        if matches!(self.peek().map(|s| &s.token), Some(Token::Not)) {
+            self.next();
+            let inner = self.parse_unary()?;
+            return Ok(TimelineQuery::Not(Box::new(inner)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> std::result::Result<TimelineQuery, QueryParseError> {
+        let tok = self.next().ok_or_else(|| QueryParseError {
+            position: self.end_position(),
+            message: "unexpected end of query".into(),
+        })?;
+
+        match tok.token {
+            Token::LParen => {
+                let inner = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Token::Favorited => Ok(TimelineQuery::Favorited),
+            Token::Ident(ref name) => {
+                let list = self.parse_in_list()?;
+                match name.as_str() {
+                    "tag" => Ok(TimelineQuery::Tag(list)),
+                    "author" => Ok(TimelineQuery::Author(list)),
+                    "lang" => Ok(TimelineQuery::Lang(list)),
+                    "word" => Ok(TimelineQuery::Word(list)),
+                    "prefix" => Ok(TimelineQuery::Prefix(list)),
+                    other => Err(QueryParseError {
+                        position: tok.position,
+                        message: format!("unknown predicate `{}`", other),
+                    }),
+                }
+            }
+            other => Err(QueryParseError {
+                position: tok.position,
+                message: format!("expected a predicate, found `{}`", other.lexeme()),
+            }),
+        }
+    }
+
+    fn parse_in_list(&mut self) -> std::result::Result<ListRef, QueryParseError> {
+        self.expect(Token::In)?;
+        match self.peek().map(|s| &s.token) {
+            Some(Token::At) => {
+                self.next();
+                let name = self.expect_ident()?;
+                Ok(ListRef::Named(name))
+            }
+            Some(Token::LBracket) => {
+                self.next();
+                let mut values = Vec::new();
+                loop {
+                    match self.next() {
+                        Some(Spanned { token: Token::Str(s), .. }) => values.push(s),
+                        Some(other) => {
+                            return Err(QueryParseError {
+                                position: other.position,
+                                message: format!("expected a string, found `{}`", other.lexeme()),
+                            });
+                        }
+                        None => {
+                            return Err(QueryParseError {
+                                position: self.end_position(),
+                                message: "unterminated list".into(),
+                            });
+                        }
+                    }
+                    match self.next() {
+                        Some(Spanned { token: Token::Comma, .. }) => continue,
+                        Some(Spanned { token: Token::RBracket, .. }) => break,
+                        Some(other) => {
+                            return Err(QueryParseError {
+                                position: other.position,
+                                message: format!("expected `,` or `]`, found `{}`", other.lexeme()),
+                            });
+                        }
+                        None => {
+                            return Err(QueryParseError {
+                                position: self.end_position(),
+                                message: "unterminated list".into(),
+                            });
+                        }
+                    }
+                }
+                Ok(ListRef::Inline(values))
+            }
+            Some(other) => Err(QueryParseError {
+                position: self.peek().unwrap().position,
+                message: format!("expected `[` or `@`, found `{}`", other.lexeme()),
+            }),
+            None => Err(QueryParseError {
+                position: self.end_position(),
+                message: "expected a list after `in`".into(),
+            }),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> std::result::Result<(), QueryParseError> {
+        match self.next() {
+            Some(ref s) if s.token == expected => Ok(()),
+            Some(other) => Err(QueryParseError {
+                position: other.position,
+                message: format!("expected `{}`, found `{}`", expected.lexeme(), other.lexeme()),
+            }),
+            None => Err(QueryParseError {
+                position: self.end_position(),
+                message: format!("expected `{}`", expected.lexeme()),
+            }),
+        }
+    }
+
+    fn expect_ident(&mut self) -> std::result::Result<String, QueryParseError> {
+        match self.next() {
+            Some(Spanned { token: Token::Ident(name), .. }) => Ok(name),
+            Some(other) => Err(QueryParseError {
+                position: other.position,
+                message: format!("expected a name, found `{}`", other.lexeme()),
+            }),
+            None => Err(QueryParseError {
+                position: self.end_position(),
+                message: "expected a name".into(),
+            }),
+        }
+    }
+}
+
+// --- message handlers ------------------------------------------------------
+
+/// Save a named timeline for a user. The query is parsed up front so malformed
+/// queries are rejected before they are ever stored.
+pub struct CreateTimeline {
+    pub auth: Auth,
+    pub name: String,
+    pub query: String,
+}
+
+impl Message for CreateTimeline {
+    type Result = Result<Timeline>;
+}
+
+impl Handler<CreateTimeline> for DbExecutor {
+    type Result = Result<Timeline>;
+
+    fn handle(&mut self, msg: CreateTimeline, _: &mut Self::Context) -> Self::Result {
+        use crate::schema::timelines;
+
+        let conn = &self.0.get()?;
+
+        // Fail fast on a malformed query, or one that references a list the
+        // user has not defined or of the wrong kind, rather than storing it and
+        // blowing up at read time. The resolved AST is discarded; only the
+        // original query string is persisted.
+        TimelineQuery::parse(&msg.query)?.resolve(msg.auth.user.id, conn)?;
+
+        let new_timeline = NewTimeline {
+            id: Uuid::new_v4(),
+            user_id: msg.auth.user.id,
+            name: msg.name,
+            query: msg.query,
+        };
+
+        let timeline = diesel::insert_into(timelines::table)
+            .values(&new_timeline)
+            .get_result::<Timeline>(conn)?;
+
+        Ok(timeline)
+    }
+}
+
+/// Read back the articles matching a stored timeline, newest first.
+pub struct GetTimeline {
+    pub auth: Auth,
+    pub name: String,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl Message for GetTimeline {
+    type Result = Result<ArticleListResponse>;
+}
+
+impl Handler<GetTimeline> for DbExecutor {
+    type Result = Result<ArticleListResponse>;
+
+    fn handle(&mut self, msg: GetTimeline, _: &mut Self::Context) -> Self::Result {
+        use crate::schema::{articles, timelines};
+
+        let conn = &self.0.get()?;
+
+        let user_id = msg.auth.user.id;
+
+        let timeline = timelines::table
+            .filter(timelines::user_id.eq(user_id))
+            .filter(timelines::name.eq(&msg.name))
+            .get_result::<Timeline>(conn)?;
+
+        let query = TimelineQuery::parse(&timeline.query)?.resolve(user_id, conn)?;
+
+        let articles_list = articles::table
+            .into_boxed()
+            .filter(query.compile(user_id))
+            .order(articles::created_at.desc())
+            .limit(msg.limit as i64)
+            .offset(msg.offset as i64)
+            .load::<Article>(conn)?;
+
+        let mut articles = Vec::with_capacity(articles_list.len());
+
+        for article in articles_list {
+            let author = users_table_find(article.author_id, conn)?;
+
+            let (favorited, following) =
+                get_favorited_and_following(article.id, author.id, user_id, conn)?;
+            let favorites_count = get_favorites_count(article.id, conn)?;
+            let tags = select_tags_on_article(article.id, conn)?;
+
+            articles.push(ArticleResponseInner {
+                slug: article.slug,
+                title: article.title,
+                description: article.description,
+                body: article.body,
+                lang: article.lang.clone(),
+                tag_list: tags,
+                created_at: CustomDateTime(article.created_at),
+                updated_at: CustomDateTime(article.updated_at),
+                favorited,
+                favorites_count,
+                author: ProfileResponseInner {
+                    username: author.username,
+                    bio: author.bio,
+                    image: author.image,
+                    following,
+                },
+            });
+        }
+
+        Ok(ArticleListResponse {
+            articles_count: articles.len(),
+            articles,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_compound_query() {
+        let query =
+            TimelineQuery::parse(r#"tag in ["rust", "actix"] and author in ["alice"] and not favorited"#)
+                .unwrap();
+
+        let expected = TimelineQuery::And(
+            Box::new(TimelineQuery::And(
+                Box::new(TimelineQuery::Tag(ListRef::Inline(vec![
+                    "rust".into(),
+                    "actix".into(),
+                ]))),
+                Box::new(TimelineQuery::Author(ListRef::Inline(vec!["alice".into()]))),
+            )),
+            Box::new(TimelineQuery::Not(Box::new(TimelineQuery::Favorited))),
+        );
+
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn parses_favorited_and_or() {
+        let query = TimelineQuery::parse(r#"favorited or lang in ["en"]"#).unwrap();
+        assert_eq!(
+            query,
+            TimelineQuery::Or(
+                Box::new(TimelineQuery::Favorited),
+                Box::new(TimelineQuery::Lang(ListRef::Inline(vec!["en".into()]))),
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_predicate() {
+        let err = TimelineQuery::parse(r#"colour in ["red"]"#).unwrap_err();
+        assert_eq!(err.position, 0);
+        assert!(err.message.contains("unknown predicate"));
+    }
+
+    #[test]
+    fn rejects_missing_list() {
+        let err = TimelineQuery::parse("tag in").unwrap_err();
+        assert!(err.message.contains("list"));
+    }
+
+    #[test]
+    fn reports_position_of_trailing_garbage() {
+        let err = TimelineQuery::parse(r#"favorited favorited"#).unwrap_err();
+        assert_eq!(err.position, 10);
+    }
+
+    // The SQL a predicate compiles to is rendered with `debug_query` so the
+    // matching paths can be asserted without a live database.
+    fn compiled_sql(query: &TimelineQuery) -> String {
+        use crate::schema::articles;
+        use diesel::pg::Pg;
+
+        let boxed = articles::table
+            .into_boxed::<Pg>()
+            .filter(query.compile(Uuid::nil()));
+        diesel::debug_query::<Pg, _>(&boxed).to_string()
+    }
+
+    #[test]
+    fn tag_predicate_matches_through_article_tags() {
+        let query = TimelineQuery::Tag(ListRef::Inline(vec!["rust".into(), "actix".into()]));
+        let sql = compiled_sql(&query);
+        assert!(sql.contains("article_tags"));
+        assert!(sql.contains("tag_name"));
+    }
+
+    #[test]
+    fn author_predicate_matches_through_users() {
+        let query = TimelineQuery::Author(ListRef::Inline(vec!["alice".into()]));
+        let sql = compiled_sql(&query);
+        assert!(sql.contains("users"));
+        assert!(sql.contains("username"));
+        assert!(sql.contains("author_id"));
+    }
+
+    #[test]
+    fn favorited_predicate_matches_through_favorite_articles() {
+        let sql = compiled_sql(&TimelineQuery::Favorited);
+        assert!(sql.contains("favorite_articles"));
+    }
+
+    #[test]
+    fn compound_query_combines_predicates() {
+        let query = TimelineQuery::parse(
+            r#"tag in ["rust"] and author in ["alice"] and not favorited"#,
+        )
+        .unwrap();
+        let sql = compiled_sql(&query);
+        assert!(sql.contains("article_tags"));
+        assert!(sql.contains("users"));
+        assert!(sql.contains("favorite_articles"));
+        assert!(sql.to_uppercase().contains("NOT"));
+    }
+
+    #[test]
+    fn word_list_compiles_to_a_full_text_match() {
+        let query = TimelineQuery::Word(ListRef::Inline(vec!["rust".into(), "actix".into()]));
+        let sql = compiled_sql(&query);
+        assert!(sql.contains("search_vector"));
+        assert!(sql.contains("plainto_tsquery"));
+        // Two values are OR-ed together.
+        assert!(sql.to_uppercase().contains(" OR "));
+    }
+
+    #[test]
+    fn prefix_list_uses_the_tsquery_prefix_operator() {
+        let query = TimelineQuery::Prefix(ListRef::Inline(vec!["act".into()]));
+        let sql = compiled_sql(&query);
+        assert!(sql.contains("search_vector"));
+        assert!(sql.contains("to_tsquery"));
+    }
+
+    #[test]
+    fn prefix_lexeme_keeps_only_the_first_safe_run() {
+        assert_eq!(prefix_lexeme("act"), Some("act".to_owned()));
+        assert_eq!(prefix_lexeme("Actix"), Some("actix".to_owned()));
+        assert_eq!(prefix_lexeme("c++"), Some("c".to_owned()));
+        assert_eq!(prefix_lexeme("hello world"), Some("hello".to_owned()));
+        assert_eq!(prefix_lexeme("!!!"), None);
+    }
+
+    #[test]
+    fn empty_word_list_matches_nothing() {
+        let sql = compiled_sql(&TimelineQuery::Word(ListRef::Inline(vec![])));
+        // An empty `eq_any` renders without a full-text match.
+        assert!(!sql.contains("tsquery"));
+    }
+}